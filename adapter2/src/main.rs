@@ -1,5 +1,6 @@
 extern crate clap;
 extern crate env_logger;
+#[macro_use]
 extern crate failure;
 extern crate globset;
 extern crate walkdir;
@@ -11,6 +12,19 @@ use std::env;
 use std::mem;
 use std::path;
 
+/// Options controlling how a dynamic library is opened.
+#[derive(Clone, Copy)]
+pub struct LoadFlags {
+    /// Resolve all undefined symbols at load time (`RTLD_NOW`) instead of lazily
+    /// (`RTLD_LAZY`), so that a mismatched library fails immediately rather than deep
+    /// inside a later debug session. Ignored by the Windows loader, which always binds
+    /// a module's imports eagerly.
+    pub resolve_eager: bool,
+    /// Make the library's symbols available to subsequently loaded libraries
+    /// (`RTLD_GLOBAL`) instead of keeping them local to this handle (`RTLD_LOCAL`).
+    pub global_symbols: bool,
+}
+
 fn main() -> Result<(), failure::Error> {
     env_logger::Builder::from_default_env().init();
 
@@ -18,55 +32,62 @@ fn main() -> Result<(), failure::Error> {
         .arg(Arg::with_name("lldb").long("lldb").takes_value(true).required(true))
         .arg(Arg::with_name("port").long("port").takes_value(true))
         .arg(Arg::with_name("multi-session").long("multi-session"))
+        .arg(Arg::with_name("resolve-eager").long("resolve-eager"))
+        .arg(Arg::with_name("python").long("python").takes_value(true))
         .get_matches();
 
     let multi_session = matches.is_present("multi-session");
+    let resolve_eager = matches.is_present("resolve-eager");
     let port = matches.value_of("port").map(|s| s.parse().unwrap()).unwrap_or(0);
     let mut liblldb_path: path::PathBuf = matches.value_of("lldb").unwrap().into();
 
     if liblldb_path.is_dir() {
-        let mut builder = GlobSetBuilder::new();
-        if cfg!(windows) {
-            builder.add(Glob::new("**/bin/liblldb.dll").unwrap());
-            builder.add(Glob::new("**/bin/liblldb.*.dll").unwrap());
-        } else if cfg!(target_os = "macos") {
-            builder.add(Glob::new("**/lib/liblldb.dylib").unwrap());
-            builder.add(Glob::new("**/lib/liblldb.*.dylib").unwrap());
-        } else {
-            builder.add(Glob::new("**/lib/liblldb.so").unwrap());
-            builder.add(Glob::new("**/lib/liblldb.so.*").unwrap());
-        }
-        let matcher = builder.build().unwrap();
-        let mut found = None;
-        for entry in walkdir::WalkDir::new(&liblldb_path).follow_links(true).max_depth(2) {
-            let entry = entry?;
-            if matcher.is_match(entry.path()) {
-                found = Some(entry.into_path());
-                break;
-            }
-        }
-        liblldb_path = match found {
-            Some(path) => path,
-            None => panic!("Can't find liblldb in {:?}", liblldb_path),
-        }
+        liblldb_path = find_liblldb(&liblldb_path)?;
     }
 
+    // Make sure the dynamic loader can find liblldb's co-located dependencies: msdiaxxx.dll
+    // next to liblldb.dll on Windows, and sibling .so/.dylib files on Linux/macOS.
+    prepend_search_path(liblldb_path.parent().unwrap())?;
+
     unsafe {
-        if cfg!(windows) {
-            // Append liblldb's directory to the PATH, so that it can find msdiaxxx.dll later.
-            let mut path = env::var_os("PATH").unwrap();
-            path.push(";");
-            path.push(liblldb_path.parent().unwrap());
-            env::set_var("PATH", path);
+        // Load liblldb lazily first with RTLD_GLOBAL, so that when we load codelldb its symbol
+        // references get resolved using this instance of liblldb. Lazy binding also lets us
+        // inspect liblldb before its undefined symbols are resolved, which is what the Python
+        // probe below relies on -- with `--resolve-eager`, eager (RTLD_NOW) binding is applied
+        // afterwards.
+        let lazy = LoadFlags { resolve_eager: false, global_symbols: true };
+        let liblldb = Library::open(&liblldb_path, lazy)?;
 
-            // Pre-load python shared lib, because liblldb will need it anyways, and we can produce
-            // a better error message in case it can't be found.
-            load_library(path::Path::new("python36.dll"), false);
-        }
+        // liblldb needs a CPython runtime. If it is already satisfied -- liblldb linked or
+        // bundled its own libpython, or a matching interpreter is already in the process --
+        // `Py_Initialize` is now resolvable through the default handle, and loading a second,
+        // possibly ABI-mismatched, copy would be harmful. Only when it is missing do we
+        // preload the requested python so liblldb's lazy references resolve.
+        let python_lib = if symbol_in_default_handle("Py_Initialize") {
+            None
+        } else {
+            let python: path::PathBuf = matches.value_of("python").map(Into::into).unwrap_or_else(default_python_name);
+            let flags = LoadFlags { resolve_eager: false, global_symbols: true };
+            match Library::open(&python, flags) {
+                Ok(lib) => Some(lib),
+                Err(err) => bail!(
+                    "liblldb requires Python but {} could not be found; pass --python ({})",
+                    python.display(),
+                    err
+                ),
+            }
+        };
 
-        // Load liblldb with RTLD_GLOBAL option, so that when we load codelldb,
-        // its symbol referenes will get resolved using this instalce of liblldb.
-        load_library(&liblldb_path, true);
+        // With Python resolved, honor `--resolve-eager` by re-opening liblldb with RTLD_NOW.
+        // Opening an already-loaded library again upgrades its binding, forcing all
+        // relocations to be bound now so a mismatch surfaces immediately at startup instead
+        // of deep inside a later debug session.
+        let liblldb_eager = if resolve_eager {
+            let eager = LoadFlags { resolve_eager: true, global_symbols: true };
+            Some(Library::open(&liblldb_path, eager)?)
+        } else {
+            None
+        };
 
         // Load codelldb shared lib
         let mut codelldb_path = env::current_exe()?;
@@ -78,82 +99,433 @@ fn main() -> Result<(), failure::Error> {
         } else {
             codelldb_path.push("libcodelldb.so");
         }
-        let codelldb = load_library(&codelldb_path, false);
+        let flags = LoadFlags { resolve_eager, global_symbols: false };
+        let codelldb = Library::open(&codelldb_path, flags)?;
 
         // Find codelldb's entry point and call it.
-        let entry: unsafe extern "C" fn(u16, bool) = mem::transmute(find_symbol(codelldb, "entry"));
+        let entry: Symbol<unsafe extern "C" fn(u16, bool)> = codelldb.get("entry")?;
         entry(port, multi_session);
+
+        // `entry` may have left worker threads running that still reference code and data in
+        // liblldb/codelldb (and the preloaded python). Deliberately leak the handles so they
+        // are never unloaded for the remaining lifetime of the process -- dropping them here
+        // would `dlclose`/`FreeLibrary` libraries that are still in use.
+        mem::forget(entry);
+        mem::forget(codelldb);
+        mem::forget(liblldb_eager);
+        mem::forget(liblldb);
+        mem::forget(python_lib);
     }
 
     Ok(())
 }
 
+/// The python shared library to preload when liblldb's CPython dependency is unsatisfied
+/// and the user did not pass `--python`.
+fn default_python_name() -> path::PathBuf {
+    if cfg!(windows) {
+        "python36.dll".into()
+    } else if cfg!(target_os = "macos") {
+        "libpython3.dylib".into()
+    } else {
+        "libpython3.so".into()
+    }
+}
+
+/// Search `dir` (up to two levels deep) for a liblldb shared library and return the one
+/// with the highest version. A distribution may ship both an unversioned symlink and one
+/// or more versioned sonames (`liblldb.so.17`, `liblldb.17.0.1.dylib`, ...); picking the
+/// highest version makes the result deterministic regardless of walk order, and the
+/// unversioned file is only chosen when no versioned file is present.
+fn find_liblldb(dir: &path::Path) -> Result<path::PathBuf, failure::Error> {
+    let globs: &[&str] = if cfg!(windows) {
+        &["**/bin/liblldb.dll", "**/bin/liblldb.*.dll"]
+    } else if cfg!(target_os = "macos") {
+        &["**/lib/liblldb.dylib", "**/lib/liblldb.*.dylib"]
+    } else {
+        &["**/lib/liblldb.so", "**/lib/liblldb.so.*"]
+    };
+
+    let mut builder = GlobSetBuilder::new();
+    for glob in globs {
+        builder.add(Glob::new(glob).unwrap());
+    }
+    let matcher = builder.build().unwrap();
+
+    let mut best: Option<(Vec<u64>, path::PathBuf)> = None;
+    for entry in walkdir::WalkDir::new(dir).follow_links(true).max_depth(2) {
+        let entry = entry?;
+        if !matcher.is_match(entry.path()) {
+            continue;
+        }
+        let name = match entry.path().file_name().and_then(|name| name.to_str()) {
+            Some(name) => name,
+            None => continue,
+        };
+        let version = match parse_liblldb_version(name) {
+            Some(version) => version,
+            None => continue,
+        };
+        let better = match best {
+            Some((ref best_version, _)) => version > *best_version,
+            None => true,
+        };
+        if better {
+            best = Some((version, entry.into_path()));
+        }
+    }
+
+    match best {
+        Some((_, path)) => Ok(path),
+        None => bail!("no liblldb found under {}; looked for {}", dir.display(), globs.join(", ")),
+    }
+}
+
+/// Parse the dotted version component of a liblldb file `name` into a comparable tuple,
+/// returning `None` for a name that is not a liblldb shared library. The unversioned file
+/// (`liblldb.so`/`liblldb.dylib`/`liblldb.dll`) yields an empty tuple, which sorts below
+/// any versioned file so it is only chosen when nothing versioned is present. All three
+/// platform spellings are recognized regardless of host so the logic stays testable.
+fn parse_liblldb_version(name: &str) -> Option<Vec<u64>> {
+    let rest = name.strip_prefix("liblldb")?;
+    if !rest.starts_with('.') {
+        return None;
+    }
+    // The version sits between the `liblldb` stem and the extension: it trails the extension
+    // for `.so` (`liblldb.so.17`) but precedes it for `.dylib`/`.dll` (`liblldb.17.0.dylib`).
+    let version = if let Some(version) = rest.strip_suffix(".dylib") {
+        version.trim_start_matches('.')
+    } else if let Some(version) = rest.strip_suffix(".dll") {
+        version.trim_start_matches('.')
+    } else if rest == ".so" {
+        ""
+    } else if let Some(version) = rest.strip_prefix(".so.") {
+        version
+    } else {
+        return None;
+    };
+    Some(version.split('.').filter(|s| !s.is_empty()).map(|s| s.parse::<u64>().unwrap_or(0)).collect())
+}
+
 #[cfg(unix)]
 mod loading {
-    use std::ffi::{CStr, CString};
+    use std::env;
+    use std::ffi::{CStr, CString, OsString};
+    use std::marker::PhantomData;
+    use std::ops::Deref;
     use std::os::raw::{c_char, c_int, c_void};
+    use std::os::unix::process::CommandExt;
     use std::path::Path;
+    use std::process::Command;
+
+    use super::LoadFlags;
 
     #[link(name = "dl")]
     extern "C" {
         fn dlopen(filename: *const c_char, flag: c_int) -> *const c_void;
         fn dlsym(handle: *const c_void, symbol: *const c_char) -> *const c_void;
+        fn dlclose(handle: *const c_void) -> c_int;
         fn dlerror() -> *const c_char;
     }
+
     const RTLD_LAZY: c_int = 0x00001;
+    const RTLD_NOW: c_int = 0x00002;
     const RTLD_GLOBAL: c_int = 0x00100;
+    const RTLD_LOCAL: c_int = 0x00000;
+
+    // RTLD_DEFAULT is the pseudo-handle that searches every object already loaded into the
+    // process: the null handle on glibc, and `(void*)-2` on macOS.
+    #[cfg(target_os = "macos")]
+    const RTLD_DEFAULT: *const c_void = -2isize as *const c_void;
+    #[cfg(not(target_os = "macos"))]
+    const RTLD_DEFAULT: *const c_void = std::ptr::null();
 
-    pub unsafe fn load_library(path: &Path, global_symbols: bool) -> *const c_void {
-        let cpath = CString::new(path.as_os_str().to_str().unwrap().as_bytes()).unwrap();
-        let flags = match global_symbols {
-            true => RTLD_LAZY | RTLD_GLOBAL,
-            false => RTLD_LAZY,
+    /// Check whether `name` is already resolvable through the process's default handle,
+    /// without loading anything new.
+    pub fn symbol_in_default_handle(name: &str) -> bool {
+        let cname = match CString::new(name) {
+            Ok(cname) => cname,
+            Err(_) => return false,
         };
-        let handle = dlopen(cpath.as_ptr() as *const c_char, flags);
-        if handle.is_null() {
-            panic!("{:?}", CStr::from_ptr(dlerror()));
+        unsafe {
+            dlerror();
+            !dlsym(RTLD_DEFAULT, cname.as_ptr() as *const c_char).is_null()
         }
-        handle
     }
 
-    pub unsafe fn find_symbol(handle: *const c_void, name: &str) -> *const c_void {
-        let cname = CString::new(name).unwrap();
-        let ptr = dlsym(handle, cname.as_ptr() as *const c_char);
-        if ptr.is_null() {
-            panic!("{:?}", CStr::from_ptr(dlerror()));
+    /// Prepend `dir` to the loader's search path so that libraries opened afterwards can
+    /// resolve dependencies sitting next to them. Uses `DYLD_LIBRARY_PATH`/`DYLD_FRAMEWORK_PATH`
+    /// on macOS and `LD_LIBRARY_PATH` elsewhere.
+    ///
+    /// glibc and dyld capture the search path once at process startup, so editing the
+    /// environment has no effect on `dlopen` calls later in the same process. We therefore
+    /// set the variable(s) and re-exec ourselves exactly once (guarded by `CODELLDB_LOADER_PATH_SET`)
+    /// so that the fresh process picks up the updated path before anything is loaded.
+    ///
+    /// Caveat on macOS: dyld strips every `DYLD_*` variable across `exec` of a binary that
+    /// runs under the hardened runtime or SIP (as a signed, distributed codelldb does), so the
+    /// re-exec'd image comes up with the variable cleared and this mechanism silently no-ops.
+    /// When we detect that the variable did not survive, warn so the failure is diagnosable;
+    /// such toolchains need their co-located dependencies resolved via an rpath or an
+    /// unsigned launcher instead.
+    pub fn prepend_search_path(dir: &Path) -> Result<(), failure::Error> {
+        const GUARD: &str = "CODELLDB_LOADER_PATH_SET";
+        let vars: &[&str] = if cfg!(target_os = "macos") {
+            &["DYLD_LIBRARY_PATH", "DYLD_FRAMEWORK_PATH"]
+        } else {
+            &["LD_LIBRARY_PATH"]
+        };
+        if env::var_os(GUARD).is_some() {
+            if cfg!(target_os = "macos") && vars.iter().all(|var| env::var_os(var).is_none()) {
+                eprintln!(
+                    "warning: DYLD_LIBRARY_PATH did not survive re-exec (hardened runtime / SIP); \
+                     liblldb's co-located dependencies may fail to resolve"
+                );
+            }
+            return Ok(());
+        }
+        for var in vars {
+            let mut value = OsString::from(dir);
+            if let Some(existing) = env::var_os(var) {
+                value.push(":");
+                value.push(existing);
+            }
+            env::set_var(var, value);
+        }
+        env::set_var(GUARD, "1");
+        // `exec` replaces the current process image and only returns on failure.
+        let err = Command::new(env::current_exe()?).args(env::args_os().skip(1)).exec();
+        Err(err.into())
+    }
+
+    /// An owned handle to a dynamically loaded library. The underlying library is
+    /// unloaded via `dlclose` when the handle is dropped.
+    pub struct Library {
+        handle: *const c_void,
+    }
+
+    // The handle is just an opaque pointer into the dynamic loader, which is
+    // internally synchronized; mirror the shared_library crate and let it cross
+    // thread boundaries.
+    unsafe impl Send for Library {}
+    unsafe impl Sync for Library {}
+
+    impl Library {
+        pub unsafe fn open(path: &Path, flags: LoadFlags) -> Result<Library, failure::Error> {
+            let cpath = CString::new(path.as_os_str().to_str().unwrap().as_bytes())?;
+            let binding = if flags.resolve_eager { RTLD_NOW } else { RTLD_LAZY };
+            let scope = if flags.global_symbols { RTLD_GLOBAL } else { RTLD_LOCAL };
+            // Clear any stale error so that dlerror() reflects this dlopen call.
+            dlerror();
+            let handle = dlopen(cpath.as_ptr() as *const c_char, binding | scope);
+            if handle.is_null() {
+                bail!("failed to load {}: {}", path.display(), last_error());
+            }
+            Ok(Library { handle })
+        }
+
+        pub unsafe fn get<T>(&self, name: &str) -> Result<Symbol<T>, failure::Error> {
+            let cname = CString::new(name)?;
+            dlerror();
+            let ptr = dlsym(self.handle, cname.as_ptr() as *const c_char);
+            if ptr.is_null() {
+                bail!("failed to resolve symbol `{}`: {}", name, last_error());
+            }
+            Ok(Symbol { ptr, pd: PhantomData })
+        }
+    }
+
+    impl Drop for Library {
+        fn drop(&mut self) {
+            unsafe {
+                dlclose(self.handle);
+            }
+        }
+    }
+
+    unsafe fn last_error() -> String {
+        let err = dlerror();
+        if err.is_null() {
+            "unknown error".to_owned()
+        } else {
+            CStr::from_ptr(err).to_string_lossy().into_owned()
+        }
+    }
+
+    /// A symbol resolved from a `Library`. It borrows the library so that the
+    /// handle cannot be unloaded while the symbol is still in use.
+    pub struct Symbol<'lib, T> {
+        ptr: *const c_void,
+        pd: PhantomData<&'lib T>,
+    }
+
+    impl<'lib, T> Deref for Symbol<'lib, T> {
+        type Target = T;
+        fn deref(&self) -> &T {
+            unsafe { &*(&self.ptr as *const *const c_void as *const T) }
         }
-        ptr
     }
 }
 
 #[cfg(windows)]
 mod loading {
-    use std::ffi::CString;
+    use std::env;
+    use std::ffi::{CString, OsString};
+    use std::marker::PhantomData;
+    use std::mem;
+    use std::ops::Deref;
     use std::os::raw::{c_char, c_void};
     use std::path::Path;
+    use std::ptr;
+
+    use super::LoadFlags;
+
+    /// Prepend `dir` to the `PATH` so that libraries opened afterwards can resolve
+    /// dependencies (such as `msdiaxxx.dll`) sitting next to them. `LoadLibrary` re-reads
+    /// `PATH` on every call, so editing it in-process is enough here.
+    pub fn prepend_search_path(dir: &Path) -> Result<(), failure::Error> {
+        let mut value = OsString::from(dir);
+        if let Some(existing) = env::var_os("PATH") {
+            value.push(";");
+            value.push(existing);
+        }
+        env::set_var("PATH", value);
+        Ok(())
+    }
 
     #[link(name = "kernel32")]
     extern "system" {
         fn LoadLibraryA(filename: *const c_char) -> *const c_void;
         fn GetProcAddress(handle: *const c_void, symbol: *const c_char) -> *const c_void;
+        fn FreeLibrary(handle: *const c_void) -> i32;
         fn GetLastError() -> u32;
+        fn GetCurrentProcess() -> *const c_void;
+        fn K32EnumProcessModules(
+            process: *const c_void,
+            modules: *mut *const c_void,
+            cb: u32,
+            needed: *mut u32,
+        ) -> i32;
+    }
+
+    /// Check whether `name` is exported by any module already loaded into the process,
+    /// without loading anything new. Windows has no single default handle, so we walk the
+    /// loaded modules and probe each with `GetProcAddress`.
+    pub fn symbol_in_default_handle(name: &str) -> bool {
+        let cname = match CString::new(name) {
+            Ok(cname) => cname,
+            Err(_) => return false,
+        };
+        unsafe {
+            let process = GetCurrentProcess();
+            let mut modules: [*const c_void; 1024] = [ptr::null(); 1024];
+            let mut needed: u32 = 0;
+            let size = (modules.len() * mem::size_of::<*const c_void>()) as u32;
+            if K32EnumProcessModules(process, modules.as_mut_ptr(), size, &mut needed) == 0 {
+                return false;
+            }
+            let count = (needed as usize / mem::size_of::<*const c_void>()).min(modules.len());
+            modules[..count]
+                .iter()
+                .any(|&module| !GetProcAddress(module, cname.as_ptr() as *const c_char).is_null())
+        }
     }
 
-    pub unsafe fn load_library(path: &Path, _global_symbols: bool) -> *const c_void {
-        let cpath = CString::new(path.as_os_str().to_str().unwrap().as_bytes()).unwrap();
-        let handle = LoadLibraryA(cpath.as_ptr() as *const c_char);
-        if handle.is_null() {
-            panic!("Could not load {:?} (err={:08X})", path, GetLastError());
+    /// An owned handle to a dynamically loaded library. The underlying library is
+    /// unloaded via `FreeLibrary` when the handle is dropped.
+    pub struct Library {
+        handle: *const c_void,
+    }
+
+    unsafe impl Send for Library {}
+    unsafe impl Sync for Library {}
+
+    impl Library {
+        pub unsafe fn open(path: &Path, _flags: LoadFlags) -> Result<Library, failure::Error> {
+            // `LoadLibrary` always binds a module's imports eagerly and there is no
+            // per-handle symbol scope, so both `LoadFlags` fields are ignored here. The
+            // eager-probe that `resolve_eager` asks for is performed by `main`, which
+            // resolves codelldb's `entry` export immediately after loading it.
+            let cpath = CString::new(path.as_os_str().to_str().unwrap().as_bytes())?;
+            let handle = LoadLibraryA(cpath.as_ptr() as *const c_char);
+            if handle.is_null() {
+                bail!("failed to load {}: (err={:08X})", path.display(), GetLastError());
+            }
+            Ok(Library { handle })
+        }
+
+        pub unsafe fn get<T>(&self, name: &str) -> Result<Symbol<T>, failure::Error> {
+            let cname = CString::new(name)?;
+            let ptr = GetProcAddress(self.handle, cname.as_ptr() as *const c_char);
+            if ptr.is_null() {
+                bail!("failed to resolve symbol `{}`: (err={:08X})", name, GetLastError());
+            }
+            Ok(Symbol { ptr, pd: PhantomData })
         }
-        handle
     }
 
-    pub unsafe fn find_symbol(handle: *const c_void, name: &str) -> *const c_void {
-        let cname = CString::new(name).unwrap();
-        let ptr = GetProcAddress(handle, cname.as_ptr() as *const c_char);
-        if ptr.is_null() {
-            panic!("Could not find {} (err={:08X})", name, GetLastError());
+    impl Drop for Library {
+        fn drop(&mut self) {
+            unsafe {
+                FreeLibrary(self.handle);
+            }
+        }
+    }
+
+    /// A symbol resolved from a `Library`. It borrows the library so that the
+    /// handle cannot be unloaded while the symbol is still in use.
+    pub struct Symbol<'lib, T> {
+        ptr: *const c_void,
+        pd: PhantomData<&'lib T>,
+    }
+
+    impl<'lib, T> Deref for Symbol<'lib, T> {
+        type Target = T;
+        fn deref(&self) -> &T {
+            unsafe { &*(&self.ptr as *const *const c_void as *const T) }
         }
-        ptr
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_liblldb_version;
+
+    #[test]
+    fn parses_linux_sonames() {
+        assert_eq!(parse_liblldb_version("liblldb.so"), Some(vec![]));
+        assert_eq!(parse_liblldb_version("liblldb.so.17"), Some(vec![17]));
+        assert_eq!(parse_liblldb_version("liblldb.so.17.0.1"), Some(vec![17, 0, 1]));
+    }
+
+    #[test]
+    fn parses_macos_dylibs() {
+        assert_eq!(parse_liblldb_version("liblldb.dylib"), Some(vec![]));
+        assert_eq!(parse_liblldb_version("liblldb.17.0.1.dylib"), Some(vec![17, 0, 1]));
+    }
+
+    #[test]
+    fn parses_windows_dlls() {
+        assert_eq!(parse_liblldb_version("liblldb.dll"), Some(vec![]));
+        assert_eq!(parse_liblldb_version("liblldb.17.0.dll"), Some(vec![17, 0]));
+    }
+
+    #[test]
+    fn rejects_non_liblldb() {
+        assert_eq!(parse_liblldb_version("libfoo.so"), None);
+    }
+
+    #[test]
+    fn versioned_outranks_unversioned() {
+        // find_liblldb keeps the entry with the highest tuple. The unversioned symlink
+        // (empty tuple) sorts below any versioned file, so it wins only when nothing
+        // versioned is present; a higher soname beats a lower one numerically.
+        let unversioned = parse_liblldb_version("liblldb.so").unwrap();
+        let v16_1 = parse_liblldb_version("liblldb.so.16.1").unwrap();
+        let v17 = parse_liblldb_version("liblldb.so.17").unwrap();
+        let v17_0_1 = parse_liblldb_version("liblldb.so.17.0.1").unwrap();
+        assert!(v16_1 > unversioned);
+        assert!(v17 > v16_1);
+        assert!(v17_0_1 > v17);
     }
 }